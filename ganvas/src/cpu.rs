@@ -7,7 +7,7 @@ use winit::dpi::PhysicalSize;
 use winit::window::Window;
 use crate::impl_window_wrapper;
 
-pub struct WindowWrapper {
+pub(crate) struct WindowWrapper {
     skia_surface: Option<Surface>,
     soft_buffer_context: softbuffer::Context<Arc<Window>>,
     soft_buffer_surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
@@ -0,0 +1,301 @@
+//! A presentation backend for a bare Linux TTY with no compositor: it renders via EGL on a
+//! GBM-backed DRM device and scans the result out directly through KMS, instead of routing
+//! through a winit `Window` and `softbuffer` like the other three backends. Because there is no
+//! `Window` to wrap, [`DrmWrapper`] is not one of the [`crate::Backend`] variants dispatched
+//! through [`crate::WindowWrapper`] — callers that want it construct it directly with
+//! [`DrmWrapper::open`].
+
+use std::fs::{File, OpenOptions};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::path::Path;
+
+use drm::control::{connector, crtc, framebuffer, Device as ControlDevice, Event, Mode, ModeTypeFlags, PageFlipFlags};
+use drm::Device as DrmDevice;
+use gbm::{BufferObject, BufferObjectFlags, Device as GbmDevice, Format as GbmFormat};
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::display::Display;
+use glutin::api::egl::surface::Surface as EglSurface;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder, GlConfig};
+use glutin::context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentGlContext};
+use glutin::display::{GetGlDisplay, GlDisplay};
+use glutin::surface::{GlSurface, SurfaceAttributesBuilder, SwapInterval, WindowSurface};
+use raw_window_handle::{GbmDisplayHandle, GbmWindowHandle, RawDisplayHandle, RawWindowHandle};
+use skia_safe::gpu::gl::FramebufferInfo;
+use skia_safe::gpu::SurfaceOrigin;
+use skia_safe::{gpu, ColorType, ISize, Surface};
+
+struct Card(File);
+
+/// Picks a CRTC the connector `info` can actually drive: its currently bound encoder/CRTC if
+/// it has one, otherwise the first CRTC any of its encoders lists in `possible_crtcs`.
+fn find_crtc(card: &Card, resources: &drm::control::ResourceHandles, info: &connector::Info) -> Option<crtc::Handle> {
+    if let Some(encoder_handle) = info.current_encoder() {
+        if let Some(crtc) = card.get_encoder(encoder_handle).ok().and_then(|encoder| encoder.crtc()) {
+            return Some(crtc);
+        }
+    }
+
+    info.encoders().iter().filter_map(|handle| card.get_encoder(*handle).ok()).find_map(|encoder| {
+        resources
+            .crtcs()
+            .iter()
+            .enumerate()
+            .find(|(index, _)| encoder.possible_crtcs().bits() & (1 << index) != 0)
+            .map(|(_, &crtc)| crtc)
+    })
+}
+
+impl AsFd for Card {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl DrmDevice for Card {}
+impl ControlDevice for Card {}
+
+/// Renders into a GBM surface and scans it out through DRM-KMS, for use on a bare TTY with no
+/// Wayland/X11 compositor. `surface()`/`resize()`/`present()` mirror the other backends' API,
+/// but the size is fixed to the connector's chosen mode, so `resize` only checks that the
+/// requested size matches that mode and returns [`DrmInitError::ModeMismatch`] otherwise.
+pub struct DrmWrapper {
+    card: Card,
+    // Fields drop in declaration order, so each dependent is declared before what it needs.
+    egl_surface: EglSurface<WindowSurface>,
+    egl_context: PossiblyCurrentContext,
+    // Never read directly again, but `egl_context`/`egl_surface` must not outlive it.
+    #[allow(dead_code)]
+    egl_display: Display,
+    gbm_surface: gbm::Surface<()>,
+    // Never read directly again, but `gbm_surface` borrows from it and must not outlive it.
+    #[allow(dead_code)]
+    gbm: GbmDevice<Card>,
+    skia_context: gpu::DirectContext,
+    skia_surface: Option<Surface>,
+    connector: connector::Handle,
+    crtc: crtc::Handle,
+    mode: Mode,
+    // Holds the previously scanned-out buffer alive until the next `present` overwrites it,
+    // at which point dropping the old value here releases it back to the GBM surface.
+    #[allow(dead_code)]
+    front_buffer: Option<BufferObject<()>>,
+    current_fb: Option<framebuffer::Handle>,
+    size: ISize,
+}
+
+impl DrmWrapper {
+    /// Opens `path` (typically `/dev/dri/card0`), picks the first connected connector and its
+    /// preferred mode, and sets up rendering + scanout for it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DrmInitError> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(DrmInitError::OpenDevice)?;
+        let card = Card(file);
+
+        let resources = card.resource_handles().map_err(DrmInitError::GetResources)?;
+
+        let connector_info = resources
+            .connectors()
+            .iter()
+            .filter_map(|handle| card.get_connector(*handle, false).ok())
+            .find(|info| info.state() == connector::State::Connected)
+            .ok_or(DrmInitError::NoConnectedConnector)?;
+
+        let mode = connector_info
+            .modes()
+            .iter()
+            .find(|m| m.mode_type().contains(ModeTypeFlags::PREFERRED))
+            .or_else(|| connector_info.modes().first())
+            .copied()
+            .ok_or(DrmInitError::NoMode)?;
+
+        let connector = connector_info.handle();
+        // Picked from the connector's own encoders, not just any encoder in the resource list —
+        // a CRTC already bound to a different connector would drive the wrong pipeline.
+        let crtc = find_crtc(&card, &resources, &connector_info).ok_or(DrmInitError::NoEncoder)?;
+
+        let (width, height) = mode.size();
+        let (width, height) = (width as u32, height as u32);
+
+        let gbm = GbmDevice::new(card.as_fd()).map_err(DrmInitError::GbmDevice)?;
+        let gbm_surface = gbm
+            .create_surface::<()>(
+                width,
+                height,
+                GbmFormat::Xrgb8888,
+                BufferObjectFlags::RENDERING | BufferObjectFlags::SCANOUT,
+            )
+            .map_err(DrmInitError::GbmSurface)?;
+
+        let display_handle = RawDisplayHandle::Gbm(GbmDisplayHandle::new(gbm.as_raw_mut() as *mut _ as *mut _));
+        let egl_display = unsafe { Display::new(display_handle) }.map_err(DrmInitError::DisplayCreation)?;
+
+        let template = ConfigTemplateBuilder::default()
+            .with_alpha_size(8)
+            .with_surface_type(ConfigSurfaceTypes::WINDOW)
+            .build();
+        let config = unsafe { egl_display.find_configs(template) }
+            .map_err(DrmInitError::NoConfig)?
+            .reduce(|config, acc| if config.num_samples() > acc.num_samples() { config } else { acc })
+            .ok_or(DrmInitError::NoConfigFound)?;
+
+        let window_handle =
+            RawWindowHandle::Gbm(GbmWindowHandle::new(gbm_surface.as_raw_mut() as *mut _ as *mut _));
+
+        let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(window_handle, width.try_into().unwrap(), height.try_into().unwrap());
+        let egl_surface = unsafe { egl_display.create_window_surface(&config, &surface_attributes) }
+            .map_err(DrmInitError::SurfaceCreation)?;
+
+        let context_attributes = ContextAttributesBuilder::new().build(Some(window_handle));
+        let fallback_context_attributes =
+            ContextAttributesBuilder::new().with_context_api(ContextApi::OpenGl(None)).build(Some(window_handle));
+        let not_current = unsafe {
+            egl_display
+                .create_context(&config, &context_attributes)
+                .or_else(|_| egl_display.create_context(&config, &fallback_context_attributes))
+        }
+        .map_err(DrmInitError::ContextCreation)?;
+        let egl_context = not_current.make_current(&egl_surface).map_err(DrmInitError::ContextCreation)?;
+
+        let _ = egl_surface.set_swap_interval(&egl_context, SwapInterval::Wait(1.try_into().unwrap()));
+
+        let interface = gpu::gl::Interface::new_load_with_cstr(|name| egl_context.display().get_proc_address(name))
+            .ok_or(DrmInitError::NoInterface)?;
+        let skia_context = gpu::direct_contexts::make_gl(interface, None).ok_or(DrmInitError::NoInterface)?;
+
+        Ok(Self {
+            card,
+            gbm,
+            gbm_surface,
+            egl_display,
+            egl_surface,
+            egl_context,
+            skia_context,
+            skia_surface: None,
+            connector,
+            crtc,
+            mode,
+            front_buffer: None,
+            current_fb: None,
+            size: ISize::new(width as i32, height as i32),
+        })
+    }
+
+    /// The mode's size is fixed once the connector is chosen; this only validates that `size`
+    /// still matches it.
+    pub fn resize(&mut self, size: impl Into<(u32, u32)>) -> Result<(), DrmInitError> {
+        let (width, height) = size.into();
+        let (mode_width, mode_height) = self.mode.size();
+        if width != mode_width as u32 || height != mode_height as u32 {
+            return Err(DrmInitError::ModeMismatch);
+        }
+        Ok(())
+    }
+
+    pub fn surface(&mut self) -> &mut Surface {
+        if self.skia_surface.is_none() {
+            // The GBM surface is `Xrgb8888`, which on little-endian memory is byte-order
+            // B, G, R, X — match that with `BGRA8`/`BGRA8888` rather than `RGBA8`, or the
+            // red and blue channels would swap on screen.
+            let fb_info = FramebufferInfo { fboid: 0, format: skia_safe::gpu::gl::Format::BGRA8.into() };
+            let render_target =
+                gpu::backend_render_targets::make_gl((self.size.width, self.size.height), 0, 8, fb_info);
+            let surface = gpu::surfaces::wrap_backend_render_target(
+                &mut self.skia_context,
+                &render_target,
+                SurfaceOrigin::BottomLeft,
+                ColorType::BGRA8888,
+                None,
+                None,
+            )
+            .unwrap();
+            self.skia_surface = Some(surface);
+        }
+
+        self.skia_surface.as_mut().unwrap()
+    }
+
+    /// Flushes the frame, swaps the EGL surface (advancing the GBM surface's back buffer),
+    /// locks the new front buffer, adds it as a DRM framebuffer and page-flips the CRTC to it.
+    /// Blocks until the flip completes so the previously scanned-out buffer can be released.
+    pub fn present(&mut self) {
+        self.skia_context.flush_and_submit();
+        self.egl_surface.swap_buffers(&self.egl_context).expect("failed to swap the EGL surface");
+        self.skia_surface = None;
+
+        let bo = self.gbm_surface.lock_front_buffer().expect("no free GBM buffer available");
+        let fb = self
+            .card
+            .add_framebuffer(&bo, 24, 32)
+            .expect("failed to register the GBM buffer as a DRM framebuffer");
+
+        if self.current_fb.is_none() {
+            // First frame: there's nothing on screen yet, so set the mode directly instead of
+            // flipping.
+            self.card
+                .set_crtc(self.crtc, Some(fb), (0, 0), &[self.connector], Some(self.mode))
+                .expect("failed to set the CRTC mode");
+        } else {
+            self.card.page_flip(self.crtc, fb, PageFlipFlags::EVENT, None).expect("page flip failed");
+            for event in self.card.receive_events().expect("failed to receive DRM events") {
+                if matches!(event, Event::PageFlip(_)) {
+                    break;
+                }
+            }
+        }
+
+        if let Some(old_fb) = self.current_fb.replace(fb) {
+            let _ = self.card.destroy_framebuffer(old_fb);
+        }
+        // Dropping the previous front buffer releases it back to the GBM surface for reuse.
+        self.front_buffer = Some(bo);
+    }
+}
+
+/// A fallible step of [`DrmWrapper::open`].
+#[derive(Debug)]
+pub enum DrmInitError {
+    OpenDevice(std::io::Error),
+    GetResources(std::io::Error),
+    NoConnectedConnector,
+    NoMode,
+    NoEncoder,
+    GbmDevice(std::io::Error),
+    GbmSurface(std::io::Error),
+    DisplayCreation(glutin::error::Error),
+    NoConfig(glutin::error::Error),
+    NoConfigFound,
+    SurfaceCreation(glutin::error::Error),
+    ContextCreation(glutin::error::Error),
+    NoInterface,
+    ModeMismatch,
+}
+
+impl std::fmt::Display for DrmInitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrmInitError::OpenDevice(e) => write!(f, "failed to open the DRM device: {e}"),
+            DrmInitError::GetResources(e) => write!(f, "failed to get DRM resources: {e}"),
+            DrmInitError::NoConnectedConnector => write!(f, "no connected DRM connector was found"),
+            DrmInitError::NoMode => write!(f, "the chosen connector has no usable mode"),
+            DrmInitError::NoEncoder => write!(f, "no usable CRTC was found for the chosen connector"),
+            DrmInitError::GbmDevice(e) => write!(f, "failed to create a GBM device: {e}"),
+            DrmInitError::GbmSurface(e) => write!(f, "failed to create a GBM surface: {e}"),
+            DrmInitError::DisplayCreation(e) => write!(f, "failed to create an EGL display: {e}"),
+            DrmInitError::NoConfig(e) => write!(f, "failed to enumerate EGL configs: {e}"),
+            DrmInitError::NoConfigFound => write!(f, "no suitable EGL config is available"),
+            DrmInitError::SurfaceCreation(e) => write!(f, "failed to create an EGL window surface: {e}"),
+            DrmInitError::ContextCreation(e) => write!(f, "failed to create an EGL context: {e}"),
+            DrmInitError::NoInterface => write!(f, "failed to resolve a GL interface for Skia"),
+            DrmInitError::ModeMismatch => write!(f, "requested size doesn't match the connector's fixed mode"),
+        }
+    }
+}
+
+impl std::error::Error for DrmInitError {}
+
+impl Drop for DrmWrapper {
+    fn drop(&mut self) {
+        if let Some(fb) = self.current_fb.take() {
+            let _ = self.card.destroy_framebuffer(fb);
+        }
+    }
+}
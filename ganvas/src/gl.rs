@@ -15,7 +15,7 @@ use winit::dpi::PhysicalSize;
 use winit::window::Window;
 use crate::impl_window_wrapper;
 
-pub struct WindowWrapper {
+pub(crate) struct WindowWrapper {
     skia_context: gpu::DirectContext,
     skia_surface: Option<Surface>,
     soft_buffer_context: softbuffer::Context<Arc<Window>>,
@@ -24,8 +24,26 @@ pub struct WindowWrapper {
 }
 
 impl WindowWrapper {
-    pub fn wrap(window: Window) -> Self {
-        let devices = Device::query_devices().expect("Failed to query devices").collect::<Vec<_>>();
+    pub fn try_wrap(window: Window) -> Result<Self, (Window, GlInitError)> {
+        let skia_context = match Self::create_skia_context() {
+            Ok(skia_context) => skia_context,
+            Err(e) => return Err((window, e)),
+        };
+
+        let window = Arc::new(window);
+        let soft_buffer_context = softbuffer::Context::new(window.clone()).unwrap();
+        let soft_buffer_surface = softbuffer::Surface::new(&soft_buffer_context, window.clone()).unwrap();
+        Ok(Self {
+            skia_context,
+            skia_surface: None,
+            soft_buffer_context,
+            soft_buffer_surface,
+            size: Default::default(),
+        })
+    }
+
+    fn create_skia_context() -> Result<gpu::DirectContext, GlInitError> {
+        let devices = Device::query_devices().map_err(GlInitError::QueryDevices)?.collect::<Vec<_>>();
 
         for (index, device) in devices.iter().enumerate() {
             println!(
@@ -36,15 +54,14 @@ impl WindowWrapper {
             );
         }
 
-        let device = devices.first().expect("No available devices");
+        let device = devices.first().ok_or(GlInitError::NoDevice)?;
 
         // Create a display using the device.
-        let display =
-            unsafe { Display::with_device(device, None) }.expect("Failed to create display");
+        let display = unsafe { Display::with_device(device, None) }.map_err(GlInitError::DisplayCreation)?;
 
         let template = config_template();
         let config = unsafe { display.find_configs(template) }
-            .unwrap()
+            .map_err(GlInitError::NoConfig)?
             .reduce(
                 |config, acc| {
                     if config.num_samples() > acc.num_samples() {
@@ -54,7 +71,7 @@ impl WindowWrapper {
                     }
                 },
             )
-            .expect("No available configs");
+            .ok_or(GlInitError::NoConfigFound)?;
 
         println!("Picked a config with {} samples", config.num_samples());
 
@@ -70,34 +87,20 @@ impl WindowWrapper {
             ContextAttributesBuilder::new().with_context_api(ContextApi::OpenGl(None)).build(None);
 
         let not_current = unsafe {
-            display.create_context(&config, &context_attributes).unwrap_or_else(|_| {
-                display
-                    .create_context(&config, &fallback_context_attributes)
-                    .expect("failed to create context")
-            })
-        };
+            display
+                .create_context(&config, &context_attributes)
+                .or_else(|_| display.create_context(&config, &fallback_context_attributes))
+        }
+            .map_err(GlInitError::ContextCreation)?;
 
         // Make the context current for rendering
-        let context = not_current.make_current_surfaceless().unwrap();
+        let context = not_current.make_current_surfaceless().map_err(GlInitError::ContextCreation)?;
         println!("Context created: {:?}", context.is_current());
 
+        let interface = gpu::gl::Interface::new_load_with_cstr(|name| context.display().get_proc_address(name))
+            .ok_or(GlInitError::NoInterface)?;
 
-        let interface = gpu::gl::Interface::new_load_with_cstr(|name|{
-            context.display().get_proc_address(name)
-        }).unwrap();
-
-
-
-        let window = Arc::new(window);
-        let soft_buffer_context = softbuffer::Context::new(window.clone()).unwrap();
-        let soft_buffer_surface = softbuffer::Surface::new(&soft_buffer_context, window.clone()).unwrap();
-        Self {
-            skia_context: gpu::direct_contexts::make_gl(interface, None).unwrap(),
-            skia_surface: None,
-            soft_buffer_context,
-            soft_buffer_surface,
-            size: Default::default(),
-        }
+        gpu::direct_contexts::make_gl(interface, None).ok_or(GlInitError::NoInterface)
     }
 
     fn create_surface(&mut self, size: impl Into<PhysicalSize<u32>>) -> Surface {
@@ -118,6 +121,35 @@ impl WindowWrapper {
     }
 }
 
+/// A fallible step of [`WindowWrapper::try_wrap`] that a caller can recover from, typically by
+/// falling back to another backend.
+#[derive(Debug)]
+pub enum GlInitError {
+    QueryDevices(glutin::error::Error),
+    NoDevice,
+    DisplayCreation(glutin::error::Error),
+    NoConfig(glutin::error::Error),
+    NoConfigFound,
+    ContextCreation(glutin::error::Error),
+    NoInterface,
+}
+
+impl std::fmt::Display for GlInitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlInitError::QueryDevices(e) => write!(f, "failed to query EGL devices: {e}"),
+            GlInitError::NoDevice => write!(f, "no EGL device is available"),
+            GlInitError::DisplayCreation(e) => write!(f, "failed to create an EGL display: {e}"),
+            GlInitError::NoConfig(e) => write!(f, "failed to enumerate EGL configs: {e}"),
+            GlInitError::NoConfigFound => write!(f, "no suitable EGL config is available"),
+            GlInitError::ContextCreation(e) => write!(f, "failed to create an EGL context: {e}"),
+            GlInitError::NoInterface => write!(f, "failed to resolve a GL interface for Skia"),
+        }
+    }
+}
+
+impl std::error::Error for GlInitError {}
+
 fn config_template() -> ConfigTemplate {
     ConfigTemplateBuilder::default()
         .with_alpha_size(8)
@@ -0,0 +1,238 @@
+mod cpu;
+mod drm;
+mod gl;
+mod vulkan;
+
+use std::ops::Deref;
+
+use skia_safe::Surface;
+use softbuffer::SoftBufferError;
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+pub use drm::{DrmInitError, DrmWrapper};
+pub use gl::GlInitError;
+pub use vulkan::{AdapterInfo, AdapterOptions, AshGraphics, GraphicsContext, InstanceFlags, VulkanInitError};
+
+/// Which graphics backend a [`WindowWrapper`] draws through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Vulkan,
+    Gl,
+    Raster,
+}
+
+/// Why a specific backend failed to initialize. The raster backend never fails, so it has no
+/// variant here.
+#[derive(Debug)]
+pub enum WrapError {
+    Vulkan(VulkanInitError),
+    Gl(GlInitError),
+}
+
+impl std::fmt::Display for WrapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrapError::Vulkan(e) => write!(f, "Vulkan backend unavailable: {e}"),
+            WrapError::Gl(e) => write!(f, "GL backend unavailable: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for WrapError {}
+
+enum Inner {
+    Vulkan(vulkan::WindowWrapper),
+    Gl(gl::WindowWrapper),
+    Raster(cpu::WindowWrapper),
+}
+
+/// A window paired with a Skia surface, drawing through whichever [`Backend`] it was created
+/// with. `surface()`, `resize()` and `present()` dispatch to that backend's implementation.
+pub struct WindowWrapper {
+    inner: Inner,
+}
+
+impl WindowWrapper {
+    /// Creates a window wrapper for a specific backend, using the default [`AdapterOptions`]
+    /// and no [`InstanceFlags`]. Returns an error rather than falling back to another backend;
+    /// use [`WindowWrapper::new_auto`] for an ordered fallback chain, or
+    /// [`WindowWrapper::new_with_backend_and_options`] to control Vulkan adapter selection and
+    /// diagnostics.
+    pub fn new_with_backend(window: Window, backend: Backend) -> Result<Self, WrapError> {
+        Self::new_with_backend_and_options(window, backend, AdapterOptions::default(), InstanceFlags::default())
+    }
+
+    /// Like [`WindowWrapper::new_with_backend`], but `adapter_options` and `instance_flags` let
+    /// a caller control the Vulkan backend's physical device choice and whether validation
+    /// layers and a debug messenger are enabled. Both are ignored by the GL and raster
+    /// backends.
+    pub fn new_with_backend_and_options(
+        window: Window,
+        backend: Backend,
+        adapter_options: AdapterOptions,
+        instance_flags: InstanceFlags,
+    ) -> Result<Self, WrapError> {
+        let inner = match backend {
+            Backend::Vulkan => Inner::Vulkan(
+                vulkan::WindowWrapper::try_wrap(window, adapter_options, instance_flags)
+                    .map_err(|(_, e)| WrapError::Vulkan(e))?,
+            ),
+            Backend::Gl => {
+                Inner::Gl(gl::WindowWrapper::try_wrap(window).map_err(|(_, e)| WrapError::Gl(e))?)
+            }
+            Backend::Raster => Inner::Raster(cpu::WindowWrapper::wrap(window)),
+        };
+        Ok(Self { inner })
+    }
+
+    /// Tries each backend in turn — Vulkan, then GL, then the CPU raster path — and returns
+    /// the first that succeeds, using the default [`AdapterOptions`] and no [`InstanceFlags`].
+    /// The raster path always succeeds, so this never fails. Use
+    /// [`WindowWrapper::new_auto_with_options`] to control Vulkan adapter selection and
+    /// diagnostics.
+    pub fn new_auto(window: Window) -> Self {
+        Self::new_auto_with_options(window, AdapterOptions::default(), InstanceFlags::default())
+    }
+
+    /// Like [`WindowWrapper::new_auto`], but `adapter_options` and `instance_flags` let a
+    /// caller control the Vulkan backend's physical device choice and whether validation
+    /// layers and a debug messenger are enabled. Both are ignored by the GL and raster
+    /// backends.
+    pub fn new_auto_with_options(window: Window, adapter_options: AdapterOptions, instance_flags: InstanceFlags) -> Self {
+        let window = match vulkan::WindowWrapper::try_wrap(window, adapter_options, instance_flags) {
+            Ok(w) => return Self { inner: Inner::Vulkan(w) },
+            Err((window, e)) => {
+                log::warn!("{}", WrapError::Vulkan(e));
+                window
+            }
+        };
+
+        let window = match gl::WindowWrapper::try_wrap(window) {
+            Ok(w) => return Self { inner: Inner::Gl(w) },
+            Err((window, e)) => {
+                log::warn!("{}", WrapError::Gl(e));
+                window
+            }
+        };
+
+        Self { inner: Inner::Raster(cpu::WindowWrapper::wrap(window)) }
+    }
+
+    /// Wraps `window` using a [`GraphicsContext`] created once and shared across several
+    /// windows, so they draw through the same Vulkan instance/device and Skia `DirectContext`
+    /// instead of each standing up their own.
+    pub fn wrap_with_context(window: Window, context: &GraphicsContext) -> Result<Self, WrapError> {
+        Ok(Self {
+            inner: Inner::Vulkan(
+                vulkan::WindowWrapper::wrap_with_context(window, context).map_err(|(_, e)| WrapError::Vulkan(e))?,
+            ),
+        })
+    }
+
+    pub fn resize(&mut self, size: impl Into<PhysicalSize<u32>>) -> Result<(), SoftBufferError> {
+        match &mut self.inner {
+            Inner::Vulkan(w) => w.resize(size),
+            Inner::Gl(w) => w.resize(size),
+            Inner::Raster(w) => w.resize(size),
+        }
+    }
+
+    pub fn surface(&mut self) -> &mut Surface {
+        match &mut self.inner {
+            Inner::Vulkan(w) => w.surface(),
+            Inner::Gl(w) => w.surface(),
+            Inner::Raster(w) => w.surface(),
+        }
+    }
+
+    pub fn present(&mut self) {
+        match &mut self.inner {
+            Inner::Vulkan(w) => w.present(),
+            Inner::Gl(w) => w.present(),
+            Inner::Raster(w) => w.present(),
+        }
+    }
+}
+
+impl AsRef<Window> for WindowWrapper {
+    fn as_ref(&self) -> &Window {
+        match &self.inner {
+            Inner::Vulkan(w) => w.as_ref(),
+            Inner::Gl(w) => w.as_ref(),
+            Inner::Raster(w) => w.as_ref(),
+        }
+    }
+}
+
+impl Deref for WindowWrapper {
+    type Target = Window;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_ref()
+    }
+}
+
+/// Implements the shared `resize`/`surface`/`present`/`AsRef`/`Deref` boilerplate for a
+/// backend's local `WindowWrapper`, which draws into an offscreen Skia surface (raster or GPU)
+/// and copies its pixels to the window through `softbuffer`. Must be invoked from a module that has a `WindowWrapper`
+/// struct with `skia_surface`, `soft_buffer_surface`, `soft_buffer_context` and `size` fields,
+/// plus a `create_surface` method.
+macro_rules! impl_window_wrapper {
+    () => {
+        impl WindowWrapper {
+            pub fn resize(&mut self, size: impl Into<winit::dpi::PhysicalSize<u32>>) -> Result<(), softbuffer::SoftBufferError> {
+                let size = size.into();
+                let width = std::num::NonZeroU32::new(size.width).unwrap();
+                let height = std::num::NonZeroU32::new(size.height).unwrap();
+                match self.soft_buffer_surface.resize(width, height) {
+                    Ok(_) => {
+                        let surface = self.create_surface(size);
+                        self.skia_surface = Some(surface);
+                        self.size = skia_safe::ISize::new(size.width as i32, size.height as i32);
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+
+            pub fn surface(&mut self) -> &mut skia_safe::Surface {
+                if let Some(surface) = &mut self.skia_surface {
+                    surface
+                } else {
+                    panic!("Surface not created. Please call resize first.");
+                }
+            }
+
+            pub fn present(&mut self) {
+                if let Some(surface) = &mut self.skia_surface {
+                    let mut soft_buffer = self.soft_buffer_surface.buffer_mut().unwrap();
+                    let u8_slice = bytemuck::cast_slice_mut::<u32, u8>(&mut soft_buffer);
+                    let image_info = skia_safe::ImageInfo::new_n32_premul((self.size.width, self.size.height), None);
+                    surface.read_pixels(
+                        &image_info,
+                        u8_slice,
+                        self.size.width as usize * 4,
+                        (0, 0),
+                    );
+                    soft_buffer.present().unwrap();
+                }
+            }
+        }
+
+        impl AsRef<winit::window::Window> for WindowWrapper {
+            fn as_ref(&self) -> &winit::window::Window {
+                self.soft_buffer_surface.window()
+            }
+        }
+
+        impl std::ops::Deref for WindowWrapper {
+            type Target = winit::window::Window;
+
+            fn deref(&self) -> &Self::Target {
+                self.soft_buffer_surface.window()
+            }
+        }
+    };
+}
+pub(crate) use impl_window_wrapper;
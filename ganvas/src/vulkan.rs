@@ -7,96 +7,155 @@ use ash::{
     Entry,
     Instance as AshInstance, vk::{self, Handle},
 };
+use ash::khr::surface::Instance as SurfaceLoader;
+use ash::khr::swapchain::Device as SwapchainLoader;
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use skia_safe::{gpu, ImageInfo, ISize, Surface};
 use softbuffer::SoftBufferError;
 use winit::dpi::PhysicalSize;
 use winit::window::Window;
 
-pub struct WindowWrapper {
+/// Number of frames that may be recorded but not yet presented at once.
+const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+pub(crate) struct WindowWrapper {
     skia_context: gpu::DirectContext,
     skia_surface: Option<Surface>,
-    ash_graphics: AshGraphics,
+    ash_graphics: Arc<AshGraphics>,
+    surface: vk::SurfaceKHR,
+    swapchain: Option<Swapchain>,
+    window: Arc<Window>,
+    // Kept around as a fallback for the (rare) case a swapchain can't be created for this surface.
     soft_buffer_context: softbuffer::Context<Arc<Window>>,
     soft_buffer_surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+    use_swapchain: bool,
+    current_frame: usize,
+    acquired_image: Option<u32>,
     size: ISize
 }
 
 impl WindowWrapper {
-    pub fn wrap(window: Window) -> Self {
-        let ash_graphics = unsafe { AshGraphics::new("skia-org") };
-        let skia_context = {
-            let get_proc = |of| unsafe {
-                match ash_graphics.get_proc(of) {
-                    Some(f) => f as _,
-                    None => {
-                        println!("resolve of {} failed", of.name().to_str().unwrap());
-                        ptr::null()
-                    }
-                }
-            };
+    pub fn try_wrap(
+        window: Window,
+        adapter_options: AdapterOptions,
+        instance_flags: InstanceFlags,
+    ) -> Result<Self, (Window, VulkanInitError)> {
+        let ash_graphics = match unsafe { AshGraphics::try_new("skia-org", &window, adapter_options, instance_flags) } {
+            Ok(ash_graphics) => ash_graphics,
+            Err(e) => return Err((window, e)),
+        };
 
-            let backend_context = unsafe {
-                gpu::vk::BackendContext::new(
-                    ash_graphics.instance.handle().as_raw() as _,
-                    ash_graphics.physical_device.as_raw() as _,
-                    ash_graphics.device.handle().as_raw() as _,
-                    (
-                        ash_graphics.queue_and_index.0.as_raw() as _,
-                        ash_graphics.queue_and_index.1,
-                    ),
-                    &get_proc,
-                )
-            };
+        let surface = match unsafe { ash_graphics.create_surface_for(&window) } {
+            Ok(surface) => surface,
+            Err(e) => return Err((window, e)),
+        };
 
-            gpu::direct_contexts::make_vulkan(&backend_context, None).unwrap()
+        let skia_context = unsafe { ash_graphics.make_skia_context() };
+        Ok(Self::from_parts(window, Arc::new(ash_graphics), surface, skia_context))
+    }
+
+    /// Wraps `window` using an [`AshGraphics`] and `DirectContext` created once and shared by
+    /// other windows, instead of standing up its own Vulkan instance/device. Only the window's
+    /// own surface and swapchain are created here.
+    pub fn wrap_with_context(window: Window, context: &GraphicsContext) -> Result<Self, (Window, VulkanInitError)> {
+        let surface = match unsafe { context.ash_graphics.create_surface_for(&window) } {
+            Ok(surface) => surface,
+            Err(e) => return Err((window, e)),
         };
 
+        Ok(Self::from_parts(window, context.ash_graphics.clone(), surface, context.skia_context.clone()))
+    }
+
+    fn from_parts(
+        window: Window,
+        ash_graphics: Arc<AshGraphics>,
+        surface: vk::SurfaceKHR,
+        skia_context: gpu::DirectContext,
+    ) -> Self {
         let window = Arc::new(window);
         let soft_buffer_context = softbuffer::Context::new(window.clone()).unwrap();
-        let mut soft_buffer_surface = softbuffer::Surface::new(&soft_buffer_context, window).unwrap();
+        let soft_buffer_surface = softbuffer::Surface::new(&soft_buffer_context, window.clone()).unwrap();
 
         Self {
             skia_context,
             skia_surface: None,
             ash_graphics,
+            surface,
+            swapchain: None,
+            window,
             soft_buffer_context,
             soft_buffer_surface,
+            use_swapchain: true,
+            current_frame: 0,
+            acquired_image: None,
             size: Default::default(),
         }
     }
 
     pub fn resize(&mut self, size: impl Into<PhysicalSize<u32>>) -> Result<(), SoftBufferError>{
         let size = size.into();
+
+        if self.use_swapchain {
+            match unsafe { self.ash_graphics.create_swapchain(self.surface, size, self.swapchain.take()) } {
+                Ok(swapchain) => {
+                    self.swapchain = Some(swapchain);
+                    self.size = ISize::new(size.width as i32, size.height as i32);
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::warn!("failed to create a Vulkan swapchain, falling back to CPU present: {e:?}");
+                    self.use_swapchain = false;
+                }
+            }
+        }
+
         let width = NonZeroU32::new(size.width).unwrap();
         let height = NonZeroU32::new(size.height).unwrap();
-        let result=self.soft_buffer_surface.resize(width, height);
-        match result {
+        match self.soft_buffer_surface.resize(width, height) {
             Ok(_) => {
-                let surface = self.create_surface(size);
+                let surface = self.create_raster_surface(size);
                 self.skia_surface = Some(surface);
                 self.size = ISize::new(size.width as i32, size.height as i32);
                 Ok(())
             }
-            Err(e) => {
-                return Err(e)
-            }
+            Err(e) => Err(e),
         }
     }
 
     pub fn surface(&mut self) -> &mut Surface {
-        if let Some(surface) = &mut self.skia_surface {
-            surface
-        } else {
-            panic!("Surface not created. Please call resize first.");
+        if self.use_swapchain && self.skia_surface.is_none() {
+            let frame = self.current_frame;
+            let size = PhysicalSize::new(self.size.width as u32, self.size.height as u32);
+
+            let mut swapchain = self.swapchain.take().expect("Surface not created. Please call resize first.");
+            let image_index = loop {
+                match unsafe { self.ash_graphics.acquire_next_image(&swapchain, frame) } {
+                    Ok(index) => break index,
+                    Err(vk::Result::ERROR_OUT_OF_DATE_KHR) | Err(vk::Result::SUBOPTIMAL_KHR) => {
+                        swapchain = unsafe { self.ash_graphics.create_swapchain(self.surface, size, Some(swapchain)) }
+                            .expect("failed to recreate the Vulkan swapchain");
+                    }
+                    Err(e) => panic!("failed to acquire a swapchain image: {e:?}"),
+                }
+            };
+
+            self.acquired_image = Some(image_index);
+            self.skia_surface = Some(self.wrap_swapchain_image(&swapchain, image_index as usize));
+            self.swapchain = Some(swapchain);
+        }
+
+        match &mut self.skia_surface {
+            Some(surface) => surface,
+            None => panic!("Surface not created. Please call resize first."),
         }
     }
 
-    fn create_surface(&mut self, size: impl Into<PhysicalSize<u32>>) -> Surface {
+    fn create_raster_surface(&mut self, size: impl Into<PhysicalSize<u32>>) -> Surface {
         let size = size.into();
         let width = size.width;
         let height = size.height;
         let image_info = ImageInfo::new_n32_premul((width as i32, height as i32), None);
-        let mut surface = gpu::surfaces::render_target(
+        gpu::surfaces::render_target(
             &mut self.skia_context,
             gpu::Budgeted::Yes,
             &image_info,
@@ -106,29 +165,113 @@ impl WindowWrapper {
             false,
             None,
         )
-            .unwrap();
-        surface
+            .unwrap()
+    }
+
+    fn wrap_swapchain_image(&mut self, swapchain: &Swapchain, image_index: usize) -> Surface {
+        let image = swapchain.images[image_index];
+        let alloc = gpu::vk::Alloc::default();
+        let image_info = unsafe {
+            gpu::vk::ImageInfo::new(
+                image.as_raw() as _,
+                alloc,
+                gpu::vk::ImageTiling::OPTIMAL,
+                gpu::vk::ImageLayout::UNDEFINED,
+                gpu::vk::Format::from(swapchain.format.format.as_raw()),
+                1,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+
+        let render_target = gpu::BackendRenderTarget::new_vulkan(
+            (swapchain.extent.width as i32, swapchain.extent.height as i32),
+            &image_info,
+        );
+
+        gpu::surfaces::wrap_backend_render_target(
+            &mut self.skia_context,
+            &render_target,
+            gpu::SurfaceOrigin::TopLeft,
+            skia_safe::ColorType::BGRA8888,
+            None,
+            None,
+        )
+            .unwrap()
     }
 
     pub fn present(&mut self){
+        if !self.use_swapchain {
+            if let Some(surface) = &mut self.skia_surface {
+                let mut soft_buffer = self.soft_buffer_surface.buffer_mut().unwrap();
+                let u8_slice = bytemuck::cast_slice_mut::<u32, u8>(&mut soft_buffer);
+                let image_info = ImageInfo::new_n32_premul((self.size.width, self.size.height), None);
+                surface.read_pixels(
+                    &image_info,
+                    u8_slice,
+                    self.size.width as usize * 4,
+                    (0, 0),
+                );
+                soft_buffer.present().unwrap();
+            }
+            return;
+        }
+
+        let (Some(swapchain), Some(image_index)) = (&self.swapchain, self.acquired_image) else { return };
+        let frame = self.current_frame;
+
         if let Some(surface) = &mut self.skia_surface {
-            let mut soft_buffer = self.soft_buffer_surface.buffer_mut().unwrap();
-            let u8_slice = bytemuck::cast_slice_mut::<u32, u8>(&mut soft_buffer);
-            let image_info = ImageInfo::new_n32_premul((self.size.width, self.size.height), None);
-            surface.read_pixels(
-                &image_info,
-                u8_slice,
-                self.size.width as usize * 4,
-                (0, 0),
+            // Make Skia's GPU work wait on the "image available" semaphore from acquire, and
+            // have its own submit signal "render finished" and transition the image to
+            // `PRESENT_SRC_KHR`, instead of relaying both through a second, empty submit.
+            let wait_semaphore =
+                gpu::vk::BackendSemaphore::new(swapchain.image_available_semaphores[frame].as_raw());
+            self.skia_context.wait(&[wait_semaphore], Some(false));
+
+            let mut signal_semaphore =
+                gpu::vk::BackendSemaphore::new(swapchain.render_finished_semaphores[frame].as_raw());
+            let mut present_state = gpu::vk::BackendSurfaceMutableState::new(
+                gpu::vk::ImageLayout::PRESENT_SRC_KHR,
+                gpu::vk::QueueFamilyIndex::IGNORED,
             );
-            soft_buffer.present().unwrap();
+            let flush_info = gpu::FlushInfo {
+                num_semaphores: 1,
+                signal_semaphores: std::slice::from_mut(&mut signal_semaphore).as_mut_ptr(),
+                ..Default::default()
+            };
+
+            self.skia_context.flush_surface_with_access(
+                surface,
+                gpu::SurfaceAccess::Present,
+                &flush_info,
+                Some(&mut present_state),
+            );
+            // Non-blocking: stalling the CPU on full GPU completion every frame would
+            // serialize rendering and defeat `MAX_FRAMES_IN_FLIGHT`. The in-flight fence
+            // submitted below is what gates frame reuse instead.
+            self.skia_context.submit(false);
+        }
+
+        match unsafe { self.ash_graphics.submit_and_present(swapchain, frame, image_index) } {
+            Ok(()) => {}
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) | Err(vk::Result::SUBOPTIMAL_KHR) => {
+                let size = PhysicalSize::new(self.size.width as u32, self.size.height as u32);
+                let _ = self.resize(size);
+            }
+            Err(e) => log::error!("swapchain present failed: {e:?}"),
         }
+
+        self.skia_surface = None;
+        self.acquired_image = None;
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
     }
 }
 
 impl AsRef<Window> for WindowWrapper {
     fn as_ref(&self) -> &Window {
-        self.soft_buffer_surface.window()
+        &self.window
     }
 }
 
@@ -136,16 +279,161 @@ impl Deref for WindowWrapper {
     type Target = Window;
 
     fn deref(&self) -> &Self::Target {
-        self.soft_buffer_surface.window()
+        &self.window
     }
 }
 
+impl Drop for WindowWrapper {
+    fn drop(&mut self) {
+        if let Some(swapchain) = self.swapchain.take() {
+            unsafe { self.ash_graphics.destroy_swapchain(swapchain) };
+        }
+        unsafe { self.ash_graphics.surface_loader.destroy_surface(self.surface, None) };
+    }
+}
+
+/// An ash [`Instance`](AshInstance)/[`Device`](ash::Device) and Skia `DirectContext` created
+/// once and shared by every window wrapped with [`WindowWrapper::wrap_with_context`], instead
+/// of each window standing up its own. Each window still gets its own `VkSurfaceKHR` and
+/// swapchain.
+pub struct GraphicsContext {
+    ash_graphics: Arc<AshGraphics>,
+    skia_context: gpu::DirectContext,
+}
+
+impl GraphicsContext {
+    /// `window` is only used to determine the required instance extensions and to create a
+    /// throwaway surface for physical device selection; it still needs to be wrapped itself
+    /// afterwards, e.g. with [`WindowWrapper::wrap_with_context`].
+    pub fn try_new(
+        app_name: &str,
+        window: &Window,
+        adapter_options: AdapterOptions,
+        instance_flags: InstanceFlags,
+    ) -> Result<Self, VulkanInitError> {
+        let ash_graphics = unsafe { AshGraphics::try_new(app_name, window, adapter_options, instance_flags)? };
+        let skia_context = unsafe { ash_graphics.make_skia_context() };
+        Ok(Self { ash_graphics: Arc::new(ash_graphics), skia_context })
+    }
+}
+
+bitflags::bitflags! {
+    /// Extra diagnostics to enable on instance creation. Each flag costs overhead, so all
+    /// default off.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct InstanceFlags: u32 {
+        /// Enable the `VK_LAYER_KHRONOS_validation` layer.
+        const VALIDATION = 1 << 0;
+        /// Enable `VK_EXT_debug_utils` and forward its messages through the `log` facade.
+        const DEBUG = 1 << 1;
+    }
+}
+
+/// Which kind of physical device to prefer when more than one is available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdapterOptions {
+    /// Prefer a discrete GPU over an integrated one.
+    #[default]
+    HighPerformance,
+    /// Prefer an integrated GPU over a discrete one.
+    LowPower,
+}
+
+impl AdapterOptions {
+    /// Higher is more preferred. Devices of a type the preference doesn't care about rank
+    /// below the preferred type but above device types neither preference ever wants.
+    fn score(self, device_type: vk::PhysicalDeviceType) -> u32 {
+        match (self, device_type) {
+            (AdapterOptions::HighPerformance, vk::PhysicalDeviceType::DISCRETE_GPU) => 3,
+            (AdapterOptions::LowPower, vk::PhysicalDeviceType::INTEGRATED_GPU) => 3,
+            (_, vk::PhysicalDeviceType::DISCRETE_GPU | vk::PhysicalDeviceType::INTEGRATED_GPU) => 2,
+            (_, vk::PhysicalDeviceType::VIRTUAL_GPU) => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Name, vendor and kind of a physical device, as reported by `vkGetPhysicalDeviceProperties`.
+#[derive(Debug, Clone)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_type: vk::PhysicalDeviceType,
+}
+
+/// A fallible step of [`AshGraphics::try_new`] that a caller can recover from, typically by
+/// falling back to another backend.
+#[derive(Debug)]
+pub enum VulkanInitError {
+    EntryLoad(ash::LoadingError),
+    MissingSurfaceExtensions(vk::Result),
+    InstanceCreation(vk::Result),
+    SurfaceCreation(vk::Result),
+    NoSuitableDevice,
+    DeviceCreation(vk::Result),
+    PresentUnsupported,
+    WindowHandle(raw_window_handle::HandleError),
+}
+
+impl std::fmt::Display for VulkanInitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VulkanInitError::EntryLoad(e) => write!(f, "failed to load the Vulkan loader: {e}"),
+            VulkanInitError::MissingSurfaceExtensions(e) => {
+                write!(f, "the required window surface extensions are unavailable: {e}")
+            }
+            VulkanInitError::InstanceCreation(e) => write!(f, "failed to create a Vulkan instance: {e}"),
+            VulkanInitError::SurfaceCreation(e) => write!(f, "failed to create a window surface: {e}"),
+            VulkanInitError::NoSuitableDevice => {
+                write!(f, "no physical device exposes both a graphics and a present queue")
+            }
+            VulkanInitError::DeviceCreation(e) => write!(f, "failed to create a Vulkan device: {e}"),
+            VulkanInitError::PresentUnsupported => {
+                write!(f, "this window's surface doesn't support presenting on the shared present queue")
+            }
+            VulkanInitError::WindowHandle(e) => write!(f, "failed to get the window's display/window handle: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for VulkanInitError {}
+
+/// Indices of the queue families a window surface needs: one supporting `GRAPHICS` and one
+/// that can present to the surface. Usually the same family, but not guaranteed to be.
+#[derive(Default, Clone, Copy)]
+pub struct QueueFamilyIndices {
+    pub graphics_family: Option<u32>,
+    pub present_family: Option<u32>,
+}
+
+impl QueueFamilyIndices {
+    fn is_complete(&self) -> bool {
+        self.graphics_family.is_some() && self.present_family.is_some()
+    }
+}
+
+pub struct Swapchain {
+    swapchain: vk::SwapchainKHR,
+    images: Vec<vk::Image>,
+    format: vk::SurfaceFormatKHR,
+    extent: vk::Extent2D,
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    in_flight_fences: Vec<vk::Fence>,
+}
+
 pub struct AshGraphics {
     pub entry: Entry,
     pub instance: AshInstance,
     pub physical_device: vk::PhysicalDevice,
     pub device: ash::Device,
     pub queue_and_index: (vk::Queue, usize),
+    pub present_queue: vk::Queue,
+    pub queue_family_indices: QueueFamilyIndices,
+    pub surface_loader: SurfaceLoader,
+    pub swapchain_loader: SwapchainLoader,
+    debug_utils_loader: Option<ash::ext::debug_utils::Instance>,
+    debug_messenger: Option<vk::DebugUtilsMessengerEXT>,
 }
 
 impl Drop for AshGraphics {
@@ -153,11 +441,37 @@ impl Drop for AshGraphics {
         unsafe {
             self.device.device_wait_idle().unwrap();
             self.device.destroy_device(None);
+            destroy_debug_messenger(&self.debug_utils_loader, self.debug_messenger);
             self.instance.destroy_instance(None);
         }
     }
 }
 
+unsafe fn destroy_debug_messenger(
+    loader: &Option<ash::ext::debug_utils::Instance>,
+    messenger: Option<vk::DebugUtilsMessengerEXT>,
+) {
+    if let (Some(loader), Some(messenger)) = (loader, messenger) {
+        loader.destroy_debug_utils_messenger(messenger, None);
+    }
+}
+
+unsafe extern "system" fn vulkan_debug_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    _user_data: *mut std::ffi::c_void,
+) -> vk::Bool32 {
+    let message = unsafe { std::ffi::CStr::from_ptr((*callback_data).p_message) }.to_string_lossy();
+    match message_severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => log::error!("[{message_type:?}] {message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => log::warn!("[{message_type:?}] {message}"),
+        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => log::info!("[{message_type:?}] {message}"),
+        _ => log::debug!("[{message_type:?}] {message}"),
+    }
+    vk::FALSE
+}
+
 // most code copied from here: https://github.com/MaikKlein/ash/blob/master/examples/src/lib.rs
 impl AshGraphics {
     pub fn vulkan_version() -> Option<(usize, usize, usize)> {
@@ -174,12 +488,63 @@ impl AshGraphics {
         })
     }
 
-    pub unsafe fn new(app_name: &str) -> AshGraphics {
-        let entry = Entry::load().unwrap();
+    /// Lists the physical devices Vulkan can see, without picking one or creating a window
+    /// surface, so callers can choose explicitly instead of relying on [`AdapterOptions`].
+    pub fn enumerate_adapters() -> Vec<AdapterInfo> {
+        unsafe {
+            let entry = match Entry::load() {
+                Ok(entry) => entry,
+                Err(_) => return Vec::new(),
+            };
+
+            let app_name = CString::new("skia-org").unwrap();
+            let app_info = vk::ApplicationInfo::default().application_name(&app_name).api_version(vk::make_api_version(0, 1, 1, 0));
+            let extension_names_raw =
+                [vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME.as_ptr(), vk::KHR_PORTABILITY_ENUMERATION_NAME.as_ptr()];
+            let create_info = vk::InstanceCreateInfo::default()
+                .application_info(&app_info)
+                .flags(vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR)
+                .enabled_extension_names(&extension_names_raw);
+
+            let instance = match entry.create_instance(&create_info, None) {
+                Ok(instance) => instance,
+                Err(_) => return Vec::new(),
+            };
+
+            let adapters = instance
+                .enumerate_physical_devices()
+                .unwrap_or_default()
+                .iter()
+                .map(|physical_device| {
+                    let properties = instance.get_physical_device_properties(*physical_device);
+                    let name = std::ffi::CStr::from_ptr(properties.device_name.as_ptr())
+                        .to_string_lossy()
+                        .into_owned();
+                    AdapterInfo { name, vendor_id: properties.vendor_id, device_type: properties.device_type }
+                })
+                .collect();
+
+            instance.destroy_instance(None);
+            adapters
+        }
+    }
+
+    pub unsafe fn try_new(
+        app_name: &str,
+        window: &Window,
+        adapter_options: AdapterOptions,
+        instance_flags: InstanceFlags,
+    ) -> Result<AshGraphics, VulkanInitError> {
+        let entry = Entry::load().map_err(VulkanInitError::EntryLoad)?;
 
         // Minimum version supported by Skia.
         let minimum_version = vk::make_api_version(0, 1, 1, 0);
 
+        let display_handle = window.display_handle().map_err(VulkanInitError::WindowHandle)?.as_raw();
+        let window_handle = window.window_handle().map_err(VulkanInitError::WindowHandle)?.as_raw();
+
+        let validation_layer = CString::new("VK_LAYER_KHRONOS_validation").unwrap();
+
         let instance: AshInstance = {
             let api_version = Self::vulkan_version()
                 .map(|(major, minor, patch)| {
@@ -193,13 +558,21 @@ impl AshGraphics {
                 .unwrap_or(minimum_version);
 
             let app_name = CString::new(app_name).unwrap();
-            let layer_names: [&CString; 0] = [];
-            // let layer_names: [&CString; 1] = [&CString::new("VK_LAYER_LUNARG_standard_validation").unwrap()];
-            let extension_names_raw = [
-                // These extensions are needed to support MoltenVK on macOS.
-                vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME.as_ptr(),
-                vk::KHR_PORTABILITY_ENUMERATION_NAME.as_ptr(),
-            ];
+
+            let mut layer_names: Vec<&CString> = Vec::new();
+            if instance_flags.contains(InstanceFlags::VALIDATION) {
+                layer_names.push(&validation_layer);
+            }
+
+            let mut extension_names_raw = ash_window::enumerate_required_extensions(display_handle)
+                .map_err(VulkanInitError::MissingSurfaceExtensions)?
+                .to_vec();
+            // These extensions are needed to support MoltenVK on macOS.
+            extension_names_raw.push(vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME.as_ptr());
+            extension_names_raw.push(vk::KHR_PORTABILITY_ENUMERATION_NAME.as_ptr());
+            if instance_flags.contains(InstanceFlags::DEBUG) {
+                extension_names_raw.push(ash::ext::debug_utils::NAME.as_ptr());
+            }
 
             let app_info = vk::ApplicationInfo::default()
                 .application_name(&app_name)
@@ -222,62 +595,376 @@ impl AshGraphics {
 
             entry
                 .create_instance(&create_info, None)
-                .expect("Failed to create a Vulkan instance")
+                .map_err(VulkanInitError::InstanceCreation)?
         };
 
-        let (physical_device, queue_family_index) = {
-            let physical_devices = instance
-                .enumerate_physical_devices()
-                .expect("Failed to enumerate Vulkan physical devices");
+        let (debug_utils_loader, debug_messenger) = if instance_flags.contains(InstanceFlags::DEBUG) {
+            let loader = ash::ext::debug_utils::Instance::new(&entry, &instance);
+            let messenger_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
+                .message_severity(
+                    vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                        | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                        | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+                )
+                .message_type(
+                    vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                        | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                        | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+                )
+                .pfn_user_callback(Some(vulkan_debug_callback));
+
+            match loader.create_debug_utils_messenger(&messenger_info, None) {
+                Ok(messenger) => (Some(loader), Some(messenger)),
+                Err(_) => (None, None),
+            }
+        } else {
+            (None, None)
+        };
 
+        let surface_loader = SurfaceLoader::new(&entry, &instance);
+        let surface = ash_window::create_surface(&entry, &instance, display_handle, window_handle, None)
+            .map_err(VulkanInitError::SurfaceCreation)?;
+
+        let physical_device_pick = (|| {
+            let physical_devices = instance.enumerate_physical_devices().ok()?;
             physical_devices
                 .iter()
-                .map(|physical_device| {
-                    instance
-                        .get_physical_device_queue_family_properties(*physical_device)
-                        .iter()
-                        .enumerate()
-                        .find_map(|(index, info)| {
-                            let supports_graphic =
-                                info.queue_flags.contains(vk::QueueFlags::GRAPHICS);
-                            supports_graphic.then_some((*physical_device, index))
-                        })
+                .filter_map(|physical_device| {
+                    let indices = Self::find_queue_families(&instance, &surface_loader, surface, *physical_device);
+                    if !indices.is_complete() {
+                        return None;
+                    }
+                    let properties = instance.get_physical_device_properties(*physical_device);
+                    let score = adapter_options.score(properties.device_type);
+                    Some((score, *physical_device, indices))
                 })
-                .find_map(|v| v)
-                .expect("Failed to find a suitable Vulkan device")
+                .max_by_key(|(score, ..)| *score)
+                .map(|(_, physical_device, indices)| (physical_device, indices))
+        })();
+
+        let (physical_device, queue_family_indices) = match physical_device_pick {
+            Some(found) => found,
+            None => {
+                destroy_debug_messenger(&debug_utils_loader, debug_messenger);
+                surface_loader.destroy_surface(surface, None);
+                instance.destroy_instance(None);
+                return Err(VulkanInitError::NoSuitableDevice);
+            }
         };
 
-        let device: ash::Device = {
+        let device: Result<ash::Device, vk::Result> = (|| {
             let features = vk::PhysicalDeviceFeatures::default();
 
-            let priorities = [1.0];
+            let mut unique_families = vec![queue_family_indices.graphics_family.unwrap()];
+            if queue_family_indices.present_family != queue_family_indices.graphics_family {
+                unique_families.push(queue_family_indices.present_family.unwrap());
+            }
 
-            let queue_info = [vk::DeviceQueueCreateInfo::default()
-                .queue_family_index(queue_family_index as _)
-                .queue_priorities(&priorities)];
+            let priorities = [1.0];
+            let queue_infos: Vec<_> = unique_families
+                .iter()
+                .map(|family| {
+                    vk::DeviceQueueCreateInfo::default()
+                        .queue_family_index(*family)
+                        .queue_priorities(&priorities)
+                })
+                .collect();
 
-            let device_extension_names_raw = [];
+            let device_extension_names_raw = [SwapchainLoader::NAME.as_ptr()];
 
             let device_create_info = vk::DeviceCreateInfo::default()
-                .queue_create_infos(&queue_info)
+                .queue_create_infos(&queue_infos)
                 .enabled_extension_names(&device_extension_names_raw)
                 .enabled_features(&features);
 
-            instance
-                .create_device(physical_device, &device_create_info, None)
-                .unwrap()
+            instance.create_device(physical_device, &device_create_info, None)
+        })();
+
+        let device = match device {
+            Ok(device) => device,
+            Err(e) => {
+                destroy_debug_messenger(&debug_utils_loader, debug_messenger);
+                surface_loader.destroy_surface(surface, None);
+                instance.destroy_instance(None);
+                return Err(VulkanInitError::DeviceCreation(e));
+            }
         };
 
         let queue_index: usize = 0;
-        let queue: vk::Queue = device.get_device_queue(queue_family_index as _, queue_index as _);
+        let queue: vk::Queue =
+            device.get_device_queue(queue_family_indices.graphics_family.unwrap(), queue_index as _);
+        let present_queue: vk::Queue = device.get_device_queue(queue_family_indices.present_family.unwrap(), 0);
 
-        AshGraphics {
+        let swapchain_loader = SwapchainLoader::new(&instance, &device);
+
+        // This surface only existed to pick a physical device and queue families above; the
+        // real per-window surface is created afterwards by `create_surface_for`.
+        surface_loader.destroy_surface(surface, None);
+
+        Ok(AshGraphics {
             queue_and_index: (queue, queue_index),
+            present_queue,
+            queue_family_indices,
             device,
             physical_device,
             instance,
             entry,
+            surface_loader,
+            swapchain_loader,
+            debug_utils_loader,
+            debug_messenger,
+        })
+    }
+
+    /// Creates a `VkSurfaceKHR` for `window`, sharing this `AshGraphics`'s instance and device.
+    /// Fails if the surface can't present on the queue family already chosen in [`Self::try_new`].
+    pub unsafe fn create_surface_for(&self, window: &Window) -> Result<vk::SurfaceKHR, VulkanInitError> {
+        let display_handle = window.display_handle().map_err(VulkanInitError::WindowHandle)?.as_raw();
+        let window_handle = window.window_handle().map_err(VulkanInitError::WindowHandle)?.as_raw();
+        let surface = ash_window::create_surface(&self.entry, &self.instance, display_handle, window_handle, None)
+            .map_err(VulkanInitError::SurfaceCreation)?;
+
+        let present_family = self.queue_family_indices.present_family.unwrap();
+        let supported = self
+            .surface_loader
+            .get_physical_device_surface_support(self.physical_device, present_family, surface)
+            .unwrap_or(false);
+        if !supported {
+            self.surface_loader.destroy_surface(surface, None);
+            return Err(VulkanInitError::PresentUnsupported);
+        }
+
+        Ok(surface)
+    }
+
+    /// Builds the Skia `DirectContext` that draws into this `AshGraphics`'s device. Shared by
+    /// every window using the same [`GraphicsContext`].
+    pub(crate) unsafe fn make_skia_context(&self) -> gpu::DirectContext {
+        let get_proc = |of| unsafe {
+            match self.get_proc(of) {
+                Some(f) => f as _,
+                None => {
+                    println!("resolve of {} failed", of.name().to_str().unwrap());
+                    ptr::null()
+                }
+            }
+        };
+
+        let backend_context = unsafe {
+            gpu::vk::BackendContext::new(
+                self.instance.handle().as_raw() as _,
+                self.physical_device.as_raw() as _,
+                self.device.handle().as_raw() as _,
+                (self.queue_and_index.0.as_raw() as _, self.queue_and_index.1),
+                &get_proc,
+            )
+        };
+
+        gpu::direct_contexts::make_vulkan(&backend_context, None).unwrap()
+    }
+
+    fn find_queue_families(
+        instance: &AshInstance,
+        surface_loader: &SurfaceLoader,
+        surface: vk::SurfaceKHR,
+        physical_device: vk::PhysicalDevice,
+    ) -> QueueFamilyIndices {
+        let mut indices = QueueFamilyIndices::default();
+
+        let queue_families = unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        for (index, info) in queue_families.iter().enumerate() {
+            let index = index as u32;
+
+            if info.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
+                indices.graphics_family = Some(index);
+            }
+
+            let present_support = unsafe {
+                surface_loader
+                    .get_physical_device_surface_support(physical_device, index, surface)
+                    .unwrap_or(false)
+            };
+            if present_support {
+                indices.present_family = Some(index);
+            }
+
+            if indices.is_complete() {
+                break;
+            }
         }
+
+        indices
+    }
+
+    /// Builds a swapchain sized to the surface's current capabilities. `old`, if given, is torn
+    /// down after the replacement is created (required by `VK_KHR_swapchain` on resize).
+    unsafe fn create_swapchain(
+        &self,
+        surface: vk::SurfaceKHR,
+        size: PhysicalSize<u32>,
+        old: Option<Swapchain>,
+    ) -> Result<Swapchain, vk::Result> {
+        let capabilities = self
+            .surface_loader
+            .get_physical_device_surface_capabilities(self.physical_device, surface)?;
+        let formats = self
+            .surface_loader
+            .get_physical_device_surface_formats(self.physical_device, surface)?;
+        let present_modes = self
+            .surface_loader
+            .get_physical_device_surface_present_modes(self.physical_device, surface)?;
+
+        let format = formats
+            .iter()
+            .find(|f| f.format == vk::Format::B8G8R8A8_UNORM && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+            .or_else(|| formats.first())
+            .copied()
+            .ok_or(vk::Result::ERROR_FORMAT_NOT_SUPPORTED)?;
+
+        // Always request FIFO: it's the only mode guaranteed to be supported and gives us
+        // vsync'd, tear-free presentation without needing to probe for MAILBOX/IMMEDIATE.
+        let present_mode = present_modes
+            .into_iter()
+            .find(|&m| m == vk::PresentModeKHR::FIFO)
+            .unwrap_or(vk::PresentModeKHR::FIFO);
+
+        let extent = if capabilities.current_extent.width != u32::MAX {
+            capabilities.current_extent
+        } else {
+            vk::Extent2D {
+                width: size.width.clamp(capabilities.min_image_extent.width, capabilities.max_image_extent.width),
+                height: size.height.clamp(capabilities.min_image_extent.height, capabilities.max_image_extent.height),
+            }
+        };
+
+        let mut image_count = capabilities.min_image_count + 1;
+        if capabilities.max_image_count > 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+
+        let indices = [
+            self.queue_family_indices.graphics_family.unwrap(),
+            self.queue_family_indices.present_family.unwrap(),
+        ];
+        let concurrent = indices[0] != indices[1];
+
+        let mut create_info = vk::SwapchainCreateInfoKHR::default()
+            .surface(surface)
+            .min_image_count(image_count)
+            .image_format(format.format)
+            .image_color_space(format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST)
+            .pre_transform(capabilities.current_transform)
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .present_mode(present_mode)
+            .clipped(true)
+            .old_swapchain(old.as_ref().map(|o| o.swapchain).unwrap_or(vk::SwapchainKHR::null()));
+
+        create_info = if concurrent {
+            create_info.image_sharing_mode(vk::SharingMode::CONCURRENT).queue_family_indices(&indices)
+        } else {
+            create_info.image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+        };
+
+        let swapchain = self.swapchain_loader.create_swapchain(&create_info, None)?;
+        let images = self.swapchain_loader.get_swapchain_images(swapchain)?;
+
+        let (image_available_semaphores, render_finished_semaphores, in_flight_fences) = match old {
+            Some(old) => {
+                let reused = (old.image_available_semaphores.clone(), old.render_finished_semaphores.clone(), old.in_flight_fences.clone());
+                // `oldSwapchain` is retired as soon as this function's create_swapchain call above
+                // returns, but any presents still in flight against it are only guaranteed to have
+                // finished once the device is idle — wait before tearing it down, same as
+                // `destroy_swapchain` does for the final swapchain.
+                self.device.device_wait_idle().unwrap();
+                self.destroy_swapchain_khr(&old);
+                reused
+            }
+            None => {
+                let semaphore_info = vk::SemaphoreCreateInfo::default();
+                let fence_info = vk::FenceCreateInfo::default().flags(vk::FenceCreateFlags::SIGNALED);
+
+                let mut image_available = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+                let mut render_finished = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+                let mut fences = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT);
+                for _ in 0..MAX_FRAMES_IN_FLIGHT {
+                    image_available.push(self.device.create_semaphore(&semaphore_info, None)?);
+                    render_finished.push(self.device.create_semaphore(&semaphore_info, None)?);
+                    fences.push(self.device.create_fence(&fence_info, None)?);
+                }
+                (image_available, render_finished, fences)
+            }
+        };
+
+        Ok(Swapchain {
+            swapchain,
+            images,
+            format,
+            extent,
+            image_available_semaphores,
+            render_finished_semaphores,
+            in_flight_fences,
+        })
+    }
+
+    /// Waits for `frame`'s fence to clear and acquires the next swapchain image, signalling
+    /// that frame's "image available" semaphore once it's ready to be drawn into.
+    unsafe fn acquire_next_image(&self, swapchain: &Swapchain, frame: usize) -> Result<u32, vk::Result> {
+        let fence = swapchain.in_flight_fences[frame];
+        self.device.wait_for_fences(&[fence], true, u64::MAX).unwrap();
+
+        let (image_index, _suboptimal) = self.swapchain_loader.acquire_next_image(
+            swapchain.swapchain,
+            u64::MAX,
+            swapchain.image_available_semaphores[frame],
+            vk::Fence::null(),
+        )?;
+
+        self.device.reset_fences(&[fence]).unwrap();
+
+        Ok(image_index)
+    }
+
+    /// Presents `image_index` once the "render finished" semaphore for this frame is
+    /// signalled, and signals that frame's fence so the next `acquire_next_image` can reuse
+    /// it. The wait on "image available" and the signal of "render finished" both already
+    /// happened inside Skia's own queue submission in `WindowWrapper::present`; this submits
+    /// an empty batch purely to get our fence signalled, since Skia's submit doesn't take an
+    /// external fence.
+    unsafe fn submit_and_present(&self, swapchain: &Swapchain, frame: usize, image_index: u32) -> Result<(), vk::Result> {
+        let fence = swapchain.in_flight_fences[frame];
+
+        let submit_info = vk::SubmitInfo::default();
+        self.device.queue_submit(self.queue_and_index.0, &[submit_info], fence).unwrap();
+
+        let swapchains = [swapchain.swapchain];
+        let image_indices = [image_index];
+        let wait_semaphores = [swapchain.render_finished_semaphores[frame]];
+        let present_info = vk::PresentInfoKHR::default()
+            .wait_semaphores(&wait_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices);
+
+        self.swapchain_loader.queue_present(self.present_queue, &present_info)?;
+
+        Ok(())
+    }
+
+    unsafe fn destroy_swapchain_khr(&self, swapchain: &Swapchain) {
+        self.swapchain_loader.destroy_swapchain(swapchain.swapchain, None);
+    }
+
+    unsafe fn destroy_swapchain(&self, swapchain: Swapchain) {
+        self.device.device_wait_idle().unwrap();
+        for &semaphore in swapchain.image_available_semaphores.iter().chain(&swapchain.render_finished_semaphores) {
+            self.device.destroy_semaphore(semaphore, None);
+        }
+        for &fence in &swapchain.in_flight_fences {
+            self.device.destroy_fence(fence, None);
+        }
+        self.destroy_swapchain_khr(&swapchain);
     }
 
     pub unsafe fn get_proc(&self, of: gpu::vk::GetProcOf) -> Option<unsafe extern "system" fn()> {
@@ -292,4 +979,48 @@ impl AshGraphics {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_performance_prefers_discrete_over_integrated() {
+        let discrete = AdapterOptions::HighPerformance.score(vk::PhysicalDeviceType::DISCRETE_GPU);
+        let integrated = AdapterOptions::HighPerformance.score(vk::PhysicalDeviceType::INTEGRATED_GPU);
+        assert!(discrete > integrated);
+    }
+
+    #[test]
+    fn low_power_prefers_integrated_over_discrete() {
+        let discrete = AdapterOptions::LowPower.score(vk::PhysicalDeviceType::DISCRETE_GPU);
+        let integrated = AdapterOptions::LowPower.score(vk::PhysicalDeviceType::INTEGRATED_GPU);
+        assert!(integrated > discrete);
+    }
+
+    #[test]
+    fn virtual_and_other_device_types_rank_below_discrete_and_integrated() {
+        for options in [AdapterOptions::HighPerformance, AdapterOptions::LowPower] {
+            let discrete = options.score(vk::PhysicalDeviceType::DISCRETE_GPU);
+            let integrated = options.score(vk::PhysicalDeviceType::INTEGRATED_GPU);
+            let virtual_gpu = options.score(vk::PhysicalDeviceType::VIRTUAL_GPU);
+            let cpu = options.score(vk::PhysicalDeviceType::CPU);
+            let other = options.score(vk::PhysicalDeviceType::OTHER);
+
+            assert!(virtual_gpu < discrete.min(integrated));
+            assert!(cpu < virtual_gpu);
+            assert_eq!(other, cpu);
+        }
+    }
+
+    #[test]
+    fn queue_family_indices_require_both_families() {
+        assert!(!QueueFamilyIndices::default().is_complete());
+
+        assert!(!QueueFamilyIndices { graphics_family: Some(0), present_family: None }.is_complete());
+        assert!(!QueueFamilyIndices { graphics_family: None, present_family: Some(0) }.is_complete());
+
+        assert!(QueueFamilyIndices { graphics_family: Some(0), present_family: Some(1) }.is_complete());
+    }
+}